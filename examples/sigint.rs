@@ -2,10 +2,9 @@ use std::error::Error;
 use std::time::Duration;
 
 use tokio::time::sleep;
-use tokio::{spawn, try_join};
 use tokio_util::sync::CancellationToken;
 
-use faucet_drain::Faucet;
+use faucet_drain::{Faucet, FaucetGroup};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -16,29 +15,40 @@ async fn main() -> Result<(), Box<dyn Error>> {
     })?;
 
     let faucet = Faucet::new_with_cancellation(5, app_cancellation.clone());
+    let group = FaucetGroup::new(faucet.clone());
 
-    let producer = spawn({
-        let faucet = faucet.clone();
-        async move {
-            for i in 1.. {
-                if faucet.push(i).await.is_break() { break; }
-                sleep(Duration::from_millis(100)).await;
+    group
+        .spawn({
+            let faucet = faucet.clone();
+            async move {
+                for i in 1.. {
+                    if faucet.push(i).await.is_break() { break; }
+                    sleep(Duration::from_millis(100)).await;
+                }
             }
-        }
-    });
+        })
+        .await?;
 
-    let consumer = spawn({
-        let faucet = faucet.clone();
-        async move {
-            while let Some(i) = faucet.next().await {
-                sleep(Duration::from_millis(500)).await;
-                let status = if faucet.is_cancelled() { "drain" } else { "got" };
-                println!("{status} #{i} ({} items waiting)", faucet.len());
+    group
+        .spawn({
+            let faucet = faucet.clone();
+            let status_faucet = faucet.clone();
+            async move {
+                faucet
+                    .for_each_drained(|i| {
+                        let status_faucet = status_faucet.clone();
+                        async move {
+                            sleep(Duration::from_millis(500)).await;
+                            let status = if status_faucet.is_cancelled() { "drain" } else { "got" };
+                            println!("{status} #{i} ({} items waiting)", status_faucet.len());
+                        }
+                    })
+                    .await;
             }
-        }
-    });
+        })
+        .await?;
 
-    try_join!(producer, consumer)?;
+    group.wait().await?;
     println!("done");
     Ok(())
 }
@@ -1,8 +1,15 @@
 use std::fmt::Debug;
+use std::future::Future;
 use std::ops::ControlFlow;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::task::{Context, Poll};
 
+use futures_core::Stream;
 use tokio::select;
+use tokio::sync::{Mutex, Notify};
+use tokio::task::{JoinError, JoinSet};
 use tokio_util::sync::CancellationToken;
 
 /// A back-pressured queue limited in size that can be drained after signaling
@@ -23,6 +30,8 @@ use tokio_util::sync::CancellationToken;
 pub struct Faucet<T> {
     queue: Arc<deadqueue::limited::Queue<T>>,
     completion: CancellationToken,
+    drain_count: Arc<AtomicUsize>,
+    drained: Arc<Notify>,
 }
 
 impl<T> Clone for Faucet<T> {
@@ -30,6 +39,8 @@ impl<T> Clone for Faucet<T> {
         Self {
             queue: self.queue.clone(),
             completion: self.completion.clone(),
+            drain_count: self.drain_count.clone(),
+            drained: self.drained.clone(),
         }
     }
 }
@@ -41,6 +52,8 @@ impl<T> Faucet<T> {
         Self {
             queue: Arc::new(deadqueue::limited::Queue::new(max_len)),
             completion: CancellationToken::new(),
+            drain_count: Arc::new(AtomicUsize::new(0)),
+            drained: Arc::new(Notify::new()),
         }
     }
 
@@ -58,6 +71,28 @@ impl<T> Faucet<T> {
         Self {
             queue: Arc::new(deadqueue::limited::Queue::new(max_len)),
             completion: cancellation,
+            drain_count: Arc::new(AtomicUsize::new(0)),
+            drained: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Creates a new, independent faucet whose completion is a
+    /// [`child_token()`] of this faucet's.
+    ///
+    /// Cancelling this (parent) faucet drains and ends every child faucet,
+    /// while ending a child leaves the parent, and any siblings, running.
+    /// Useful for e.g. spinning up per-connection faucets that all terminate
+    /// on a global shutdown signal, while individual connections can be torn
+    /// down independently.
+    ///
+    /// [`child_token()`]: CancellationToken::child_token
+    #[must_use]
+    pub fn child(&self, max_len: usize) -> Self {
+        Self {
+            queue: Arc::new(deadqueue::limited::Queue::new(max_len)),
+            completion: self.completion.child_token(),
+            drain_count: Arc::new(AtomicUsize::new(0)),
+            drained: Arc::new(Notify::new()),
         }
     }
 
@@ -100,20 +135,22 @@ impl<T> Faucet<T> {
         }
     }
 
-    /// Attempts to push a value onto the queue, returning `Err(value)` if the
-    /// queue is full or has been cancelled.
-    pub async fn try_push(&self, value: T) -> Result<(), T> {
+    /// Attempts to push a value onto the queue, distinguishing back-pressure
+    /// rejection ([`TryPushError::Full`]) from a cancelled faucet
+    /// ([`TryPushError::Cancelled`]) so a caller can retry the former and
+    /// abandon the latter.
+    pub async fn try_push(&self, value: T) -> Result<(), TryPushError<T>> {
         if self.completion.is_cancelled() {
-            return Err(value);
+            return Err(TryPushError::Cancelled(value));
         }
 
-        self.queue.try_push(value)
+        self.queue.try_push(value).map_err(TryPushError::Full)
     }
 
     /// Attempts to pop a value from the queue, returning `None` if the queue is
     /// has been cancelled and finished draining.
     pub async fn next(&self) -> Option<T> {
-        select! {
+        let value = select! {
             biased;
             _ = self.completion.cancelled() => {
                 self.queue.try_pop()
@@ -121,14 +158,49 @@ impl<T> Faucet<T> {
             x = self.queue.pop() => {
                 Some(x)
             }
-        }
+        };
+        self.notify_if_drained();
+        value
     }
 
     /// Attempts to pop a value from the queue, returning `None` if the queue is
     /// currently empty.
     #[must_use]
     pub fn try_pop(&self) -> Option<T> {
-        self.queue.try_pop()
+        let value = self.queue.try_pop();
+        self.notify_if_drained();
+        value
+    }
+
+    /// Wakes any [`FaucetGroup::wait()`] (or other [`Notify`] subscriber)
+    /// blocked on this faucet fully draining, if the queue is currently
+    /// empty.
+    fn notify_if_drained(&self) {
+        if self.queue.is_empty() {
+            self.drained.notify_waiters();
+        }
+    }
+
+    /// Applies `f` to every value popped from the queue until the faucet is
+    /// fully drained, returning the number of values processed.
+    ///
+    /// Guarantees the "complete in-flight work before shutdown" ordering:
+    /// even once the faucet is cancelled, this keeps popping until the queue
+    /// is empty, so no already-accepted item is dropped. Packages the drain
+    /// loop otherwise hand-rolled as
+    /// `while let Some(x) = faucet.next().await { ... }` into one
+    /// cancel-safe call.
+    pub async fn for_each_drained<F, Fut>(self, mut f: F) -> usize
+    where
+        F: FnMut(T) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let mut count = 0;
+        while let Some(value) = self.next().await {
+            f(value).await;
+            count += 1;
+        }
+        count
     }
 
     /// The number of items currently stored in the queue.
@@ -136,8 +208,491 @@ impl<T> Faucet<T> {
         self.queue.len()
     }
 
+    /// Returns true if the queue currently holds no items.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
     /// The maximum number of items that can be stored in the queue.
     pub fn capacity(&self) -> usize {
         self.queue.capacity()
     }
+
+    /// Returns a [`Stream`] over the faucet's values, cloning this faucet for
+    /// the returned stream.
+    ///
+    /// This is useful for feeding a faucet into combinators such as `.map`,
+    /// `.buffered`, or `.take_while` instead of hand-rolling a
+    /// `while let Some(x) = faucet.next().await` loop.
+    #[must_use]
+    pub fn stream(&self) -> FaucetStream<T>
+    where
+        T: Send + 'static,
+    {
+        self.clone().into_stream()
+    }
+
+    /// Converts this faucet into a [`Stream`] over its values.
+    #[must_use]
+    pub fn into_stream(self) -> FaucetStream<T>
+    where
+        T: Send + 'static,
+    {
+        FaucetStream {
+            faucet: self,
+            pending: None,
+        }
+    }
+
+    /// Splits this faucet into its push ([`Drain`]) and pop ([`Spout`])
+    /// halves.
+    ///
+    /// Mirrors the channel-close semantics of [`tokio::sync::mpsc`]: once
+    /// every clone of the returned [`Drain`] is dropped, the faucet is
+    /// automatically [`end()`]ed, so the [`Spout`] naturally finishes
+    /// draining without anyone explicitly signaling completion. This also
+    /// makes it a type error for a consumer holding only a [`Spout`] to push.
+    ///
+    /// The live-`Drain` count is tracked on the faucet itself, so calling
+    /// `split()` more than once on the same faucet (or on clones of it)
+    /// shares a single count: the faucet only ends once every `Drain` handed
+    /// out by any of those calls has been dropped.
+    ///
+    /// [`end()`]: Faucet::end
+    #[must_use]
+    pub fn split(&self) -> (Drain<T>, Spout<T>) {
+        self.drain_count.fetch_add(1, Ordering::SeqCst);
+        let drain = Drain {
+            faucet: self.clone(),
+            count: self.drain_count.clone(),
+        };
+        let spout = Spout {
+            faucet: self.clone(),
+        };
+        (drain, spout)
+    }
+}
+
+/// The push side of a [`Faucet`], returned by [`Faucet::split()`].
+///
+/// Automatically ends the faucet once every clone of this `Drain` has been
+/// dropped.
+#[derive(Debug)]
+pub struct Drain<T> {
+    faucet: Faucet<T>,
+    count: Arc<AtomicUsize>,
+}
+
+impl<T> Clone for Drain<T> {
+    fn clone(&self) -> Self {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        Self {
+            faucet: self.faucet.clone(),
+            count: self.count.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Drain<T> {
+    fn drop(&mut self) {
+        if self.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.faucet.end();
+        }
+    }
+}
+
+impl<T> Drain<T> {
+    /// Pushes a value onto the queue or waits until space is available.
+    pub async fn push(&self, value: T) -> ControlFlow<(), ()> {
+        self.faucet.push(value).await
+    }
+
+    /// Attempts to push a value onto the queue, distinguishing back-pressure
+    /// rejection from a cancelled faucet. See [`Faucet::try_push()`].
+    pub async fn try_push(&self, value: T) -> Result<(), TryPushError<T>> {
+        self.faucet.try_push(value).await
+    }
+
+    /// The number of items currently stored in the queue.
+    pub fn len(&self) -> usize {
+        self.faucet.len()
+    }
+
+    /// Returns true if the queue currently holds no items.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.faucet.is_empty()
+    }
+
+    /// The maximum number of items that can be stored in the queue.
+    pub fn capacity(&self) -> usize {
+        self.faucet.capacity()
+    }
+}
+
+/// The pop side of a [`Faucet`], returned by [`Faucet::split()`].
+#[derive(Debug, Clone)]
+pub struct Spout<T> {
+    faucet: Faucet<T>,
+}
+
+impl<T> Spout<T> {
+    /// Attempts to pop a value from the queue, returning `None` if the queue
+    /// has been cancelled and finished draining.
+    pub async fn next(&self) -> Option<T> {
+        self.faucet.next().await
+    }
+
+    /// Attempts to pop a value from the queue, returning `None` if the queue
+    /// is currently empty.
+    #[must_use]
+    pub fn try_pop(&self) -> Option<T> {
+        self.faucet.try_pop()
+    }
+
+    /// Applies `f` to every value popped from the queue until the faucet is
+    /// fully drained, returning the number of values processed. See
+    /// [`Faucet::for_each_drained()`].
+    pub async fn for_each_drained<F, Fut>(self, f: F) -> usize
+    where
+        F: FnMut(T) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        self.faucet.for_each_drained(f).await
+    }
+
+    /// The number of items currently stored in the queue.
+    pub fn len(&self) -> usize {
+        self.faucet.len()
+    }
+
+    /// Returns true if the queue currently holds no items.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.faucet.is_empty()
+    }
+
+    /// The maximum number of items that can be stored in the queue.
+    pub fn capacity(&self) -> usize {
+        self.faucet.capacity()
+    }
+
+    /// Returns a [`Stream`] over the faucet's values. See
+    /// [`Faucet::stream()`].
+    #[must_use]
+    pub fn stream(&self) -> FaucetStream<T>
+    where
+        T: Send + 'static,
+    {
+        self.faucet.stream()
+    }
+}
+
+/// A [`Stream`] adapter over a [`Faucet`], returned by [`Faucet::stream()`]
+/// and [`Faucet::into_stream()`].
+///
+/// Yields `Some(_)` for every queued value, continues draining after the
+/// faucet is cancelled, and yields `None` once [`Faucet::is_finished()`]
+/// holds.
+pub struct FaucetStream<T> {
+    faucet: Faucet<T>,
+    pending: Option<Pin<Box<dyn Future<Output = Option<T>> + Send>>>,
+}
+
+impl<T> Stream for FaucetStream<T>
+where
+    T: Send + 'static,
+{
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        let pending = this.pending.get_or_insert_with(|| {
+            let faucet = this.faucet.clone();
+            Box::pin(async move { faucet.next().await })
+        });
+
+        match pending.as_mut().poll(cx) {
+            Poll::Ready(value) => {
+                this.pending = None;
+                Poll::Ready(value)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.faucet.len(), None)
+    }
+}
+
+/// A [`TaskTracker`]-style orchestrator that tracks producer/consumer tasks
+/// spawned against a [`Faucet`] and awaits their full graceful shutdown.
+///
+/// Call [`FaucetGroup::wait()`] after signaling completion (e.g. via
+/// [`Faucet::end()`] or cancelling the faucet's token) to get a single
+/// future that resolves once the faucet has fully drained and every tracked
+/// task has joined, instead of hand-rolling `try_join!`.
+///
+/// [`TaskTracker`]: https://docs.rs/tokio-util/latest/tokio_util/task/task_tracker/struct.TaskTracker.html
+#[derive(Debug)]
+pub struct FaucetGroup<T> {
+    faucet: Faucet<T>,
+    tasks: Mutex<JoinSet<()>>,
+    closed: AtomicBool,
+}
+
+impl<T> FaucetGroup<T> {
+    /// Creates a new, empty group tracking tasks against the given faucet.
+    #[must_use]
+    pub fn new(faucet: Faucet<T>) -> Self {
+        Self {
+            faucet,
+            tasks: Mutex::new(JoinSet::new()),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// Spawns a future as a tracked task, returning [`Err(Closed)`] if the
+    /// group has been [closed](FaucetGroup::close).
+    ///
+    /// [`Err(Closed)`]: Closed
+    pub async fn spawn<F>(&self, future: F) -> Result<(), Closed>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(Closed);
+        }
+
+        self.tasks.lock().await.spawn(future);
+        Ok(())
+    }
+
+    /// Prevents any further tasks from being registered via
+    /// [`spawn()`](FaucetGroup::spawn).
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+
+    /// Waits until the faucet's completion token has been cancelled, the
+    /// queue has been fully drained, and every tracked task has joined.
+    ///
+    /// Returns the first [`JoinError`] encountered (e.g. from a panicking
+    /// task), if any. If no tracked task is actually draining the faucet
+    /// (e.g. a consumer exited early, or none was ever spawned), the queue
+    /// will never finish draining on its own, so this keeps waiting rather
+    /// than returning a false `Ok(())`.
+    pub async fn wait(&self) -> Result<(), JoinError> {
+        self.faucet.completion.cancelled().await;
+
+        // Wait for the queue to fully drain. The task-set lock is only held
+        // long enough to reap an already-finished task; while we're waiting
+        // on the drain notification itself it's released, so a concurrent
+        // `spawn()` doesn't block on whatever task happens to be running.
+        loop {
+            let drained = self.faucet.drained.notified();
+            if self.faucet.is_finished() {
+                break;
+            }
+
+            let joined = self.tasks.lock().await.try_join_next();
+            match joined {
+                Some(result) => result?,
+                None => drained.await,
+            }
+        }
+
+        // The queue is drained; all that's left is for tracked tasks to
+        // actually join.
+        let mut tasks = self.tasks.lock().await;
+        while let Some(result) = tasks.join_next().await {
+            result?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned by [`FaucetGroup::spawn()`] when the group has been
+/// [closed](FaucetGroup::close) and is no longer accepting new tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Closed;
+
+impl std::fmt::Display for Closed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "faucet group is closed and is not accepting new tasks")
+    }
+}
+
+impl std::error::Error for Closed {}
+
+/// Error returned by [`Faucet::try_push()`] recovering the value that could
+/// not be pushed, following the shape of
+/// [`tokio::sync::mpsc::error::TrySendError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryPushError<T> {
+    /// The queue is at capacity; the value can be retried later.
+    Full(T),
+    /// The faucet has been cancelled and is no longer accepting values.
+    Cancelled(T),
+}
+
+impl<T> TryPushError<T> {
+    /// Consumes the error, returning the value that could not be pushed.
+    pub fn into_inner(self) -> T {
+        match self {
+            TryPushError::Full(value) | TryPushError::Cancelled(value) => value,
+        }
+    }
+}
+
+impl<T> std::fmt::Display for TryPushError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TryPushError::Full(_) => write!(f, "faucet is full"),
+            TryPushError::Cancelled(_) => write!(f, "faucet is cancelled"),
+        }
+    }
+}
+
+impl<T: Debug> std::error::Error for TryPushError<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn split_shares_drain_count_across_calls() {
+        let faucet = Faucet::<u32>::new(4);
+
+        let (drain1, _spout1) = faucet.split();
+        let (drain2, spout2) = faucet.split();
+
+        drop(drain1);
+        assert!(
+            !faucet.is_cancelled(),
+            "faucet should stay open while a Drain from another split() call is alive"
+        );
+        assert!(drain2.push(1).await.is_continue());
+
+        drop(drain2);
+        assert!(
+            faucet.is_cancelled(),
+            "faucet should end once every Drain from every split() call has been dropped"
+        );
+        assert_eq!(spout2.try_pop(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn stream_yields_queued_items_then_ends_after_cancellation() {
+        let faucet = Faucet::<u32>::new(4);
+        faucet.try_push(1).await.unwrap();
+        faucet.try_push(2).await.unwrap();
+        faucet.end();
+
+        let mut stream = faucet.stream();
+        let mut items = Vec::new();
+        while let Some(item) = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await
+        {
+            items.push(item);
+        }
+
+        assert_eq!(items, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn cancelling_parent_drains_and_ends_every_child() {
+        let parent = Faucet::<u32>::new(4);
+        let child = parent.child(4);
+        let sibling = parent.child(4);
+
+        child.try_push(1).await.unwrap();
+
+        parent.end();
+
+        assert!(child.is_cancelled());
+        assert_eq!(child.try_pop(), Some(1));
+        assert!(child.is_finished());
+
+        assert!(sibling.is_cancelled());
+        assert!(sibling.is_finished());
+    }
+
+    #[tokio::test]
+    async fn ending_child_does_not_cancel_parent_or_sibling() {
+        let parent = Faucet::<u32>::new(4);
+        let child = parent.child(4);
+        let sibling = parent.child(4);
+
+        child.end();
+
+        assert!(child.is_cancelled());
+        assert!(!parent.is_cancelled());
+        assert!(!sibling.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn try_push_distinguishes_full_from_cancelled() {
+        let faucet = Faucet::<u32>::new(1);
+        faucet.try_push(1).await.unwrap();
+
+        match faucet.try_push(2).await {
+            Err(TryPushError::Full(value)) => assert_eq!(value, 2),
+            other => panic!("expected Err(Full(_)), got {other:?}"),
+        }
+
+        faucet.end();
+
+        match faucet.try_push(3).await {
+            Err(TryPushError::Cancelled(value)) => assert_eq!(value, 3),
+            other => panic!("expected Err(Cancelled(_)), got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn for_each_drained_delivers_queued_items_and_returns_count() {
+        let faucet = Faucet::<u32>::new(4);
+        faucet.try_push(1).await.unwrap();
+        faucet.try_push(2).await.unwrap();
+        faucet.try_push(3).await.unwrap();
+        faucet.end();
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let count = faucet
+            .for_each_drained(|i| {
+                let seen = seen.clone();
+                async move {
+                    seen.lock().await.push(i);
+                }
+            })
+            .await;
+
+        assert_eq!(count, 3);
+        assert_eq!(*seen.lock().await, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn group_wait_waits_for_full_drain_before_returning() {
+        let faucet = Faucet::<u32>::new(4);
+        faucet.try_push(1).await.unwrap();
+        faucet.try_push(2).await.unwrap();
+
+        let group = FaucetGroup::new(faucet.clone());
+        group
+            .spawn({
+                let faucet = faucet.clone();
+                async move {
+                    faucet.for_each_drained(|_| async {}).await;
+                }
+            })
+            .await
+            .unwrap();
+
+        faucet.end();
+        group.wait().await.unwrap();
+
+        assert!(faucet.is_finished());
+        assert_eq!(faucet.len(), 0);
+    }
 }